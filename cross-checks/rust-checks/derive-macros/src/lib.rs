@@ -0,0 +1,370 @@
+//! Implementation of `#[derive(CrossCheckHash)]`.
+//!
+//! By default, the derive builds a `CrossCheckHash` impl that aggregates
+//! the cross-check hash of every field. Three things can override that at the
+//! struct level, via `#[cross_check_hash(...)]` on the struct itself:
+//!
+//!   * `custom_hash = "path::to::fn"` replaces the generated body wholesale
+//!     with a call to a user-supplied `fn<XCHA, XCHS>(&Self, usize) -> u64`
+//!     (invoked as `path::to::fn::<XCHA, XCHS>(self, depth)`), where the
+//!     `usize` is the current recursion budget (see below).
+//!   * `depth = N` sets the recursion budget a top-level call to
+//!     `cross_check_hash()` starts with (default:
+//!     `cross_check_runtime::hash::DEFAULT_XCHECK_DEPTH`).
+//!   * `stable` hashes primitive integer fields through
+//!     `cross_check_runtime::hash::stable`'s fixed-width little-endian
+//!     writers instead of the hasher's native `write_u64`, so the same
+//!     logical value cross-checks identically across endianness and
+//!     pointer width (e.g. a 32-bit C build against a 64-bit Rust one).
+//!     Composite fields (strings, slices, nested structs) are unaffected:
+//!     they still hash through their own `CrossCheckHash` impl regardless
+//!     of this attribute.
+//!
+//! and four things can override it per field, via `#[cross_check_hash(...)]`
+//! on the field:
+//!
+//!   * `ignore` skips the field entirely (padding, timestamps, anything
+//!     that's expected to legitimately diverge between the C and Rust
+//!     builds).
+//!   * `fixed_hash = "0x..."` substitutes a constant for a field whose real
+//!     value is nondeterministic (pointers, handles, ...).
+//!   * `project = "expr"` hashes `self.field.expr` instead of `self.field`
+//!     itself, so e.g. a raw pointer field can be cross-checked via the
+//!     pointee it's known to reference.
+//!   * `no_recurse` always treats the field as opaque, hashing its own
+//!     value (via `cross_check_runtime::hash::leaf_hash`) instead of
+//!     recursing into it. Useful for pointer/handle fields that are never
+//!     safe to walk, independent of the remaining recursion budget.
+//!
+//! For fields that *are* recursed into, the derive decrements the recursion
+//! budget by one and, once it hits zero, substitutes a `leaf_hash` just like
+//! `no_recurse` would. That budget is what lets a cyclic structure (common
+//! in C-translated code, e.g. a doubly-linked list) terminate deterministically
+//! while still distinguishing structurally different values above the cutoff.
+//!
+//! Aggregated fields are separated with `CrossCheckHasher::write_separator`
+//! (a no-op for `SimpleHasher`, a delimiter byte otherwise), so e.g.
+//! `{a:1,b:2}` and `{a:12}` can't collide once their fields are hashed in
+//! sequence.
+//!
+//! An unrecognized key inside `#[cross_check_hash(...)]` is a hard error:
+//! silently ignoring a typo'd attribute would produce a cross-check that
+//! looks valid but hashes the wrong thing.
+
+extern crate proc_macro;
+#[macro_use]
+extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+
+#[proc_macro_derive(CrossCheckHash, attributes(cross_check_hash))]
+pub fn cross_check_hash_derive(input: TokenStream) -> TokenStream {
+    let source = input.to_string();
+    let ast = syn::parse_derive_input(&source)
+        .expect("could not parse item for #[derive(CrossCheckHash)]");
+    cross_check_hash_impl(&ast)
+        .parse()
+        .expect("could not parse derived CrossCheckHash impl")
+}
+
+/// Parsed `#[cross_check_hash(...)]` attribute on the struct itself.
+#[derive(Default)]
+struct StructArgs {
+    custom_hash: Option<String>,
+    depth: Option<u64>,
+    stable: bool,
+}
+
+/// Parsed `#[cross_check_hash(...)]` attribute on a single field.
+enum FieldArgs {
+    Normal,
+    Ignore,
+    FixedHash(u64),
+    Project(String),
+    NoRecurse,
+}
+
+impl Default for FieldArgs {
+    fn default() -> Self {
+        FieldArgs::Normal
+    }
+}
+
+impl FieldArgs {
+    /// The attribute key this variant was parsed from, for conflict
+    /// diagnostics. `Normal` isn't a key a user can write, so it never
+    /// shows up as one side of a reported conflict.
+    fn key(&self) -> &'static str {
+        match *self {
+            FieldArgs::Normal => "(none)",
+            FieldArgs::Ignore => "ignore",
+            FieldArgs::FixedHash(_) => "fixed_hash",
+            FieldArgs::Project(_) => "project",
+            FieldArgs::NoRecurse => "no_recurse",
+        }
+    }
+}
+
+/// Flatten every `#[cross_check_hash(...)]` attribute attached to `attrs`
+/// into the list of comma-separated items inside it.
+fn cross_check_hash_metas(attrs: &[syn::Attribute]) -> Vec<syn::NestedMetaItem> {
+    attrs
+        .iter()
+        .filter_map(|attr| match attr.value {
+            syn::MetaItem::List(ref ident, ref nested) if ident == "cross_check_hash" => {
+                Some(nested.clone())
+            }
+            _ => None,
+        })
+        .flat_map(|nested| nested.into_iter())
+        .collect()
+}
+
+fn parse_struct_args(attrs: &[syn::Attribute]) -> StructArgs {
+    let mut args = StructArgs::default();
+    for meta in cross_check_hash_metas(attrs) {
+        match meta {
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+                ref ident,
+                syn::Lit::Str(ref s, _),
+            )) if ident == "custom_hash" => {
+                args.custom_hash = Some(s.clone());
+            }
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+                ref ident,
+                syn::Lit::Int(n, _),
+            )) if ident == "depth" => {
+                args.depth = Some(n);
+            }
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident))
+                if ident == "stable" =>
+            {
+                args.stable = true;
+            }
+            _ => panic!("unknown #[cross_check_hash] struct attribute: {:?}", meta),
+        }
+    }
+    args
+}
+
+fn parse_field_args(attrs: &[syn::Attribute]) -> FieldArgs {
+    let mut args = FieldArgs::default();
+    for meta in cross_check_hash_metas(attrs) {
+        let parsed = match meta {
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident))
+                if ident == "ignore" =>
+            {
+                FieldArgs::Ignore
+            }
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::Word(ref ident))
+                if ident == "no_recurse" =>
+            {
+                FieldArgs::NoRecurse
+            }
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+                ref ident,
+                syn::Lit::Str(ref s, _),
+            )) if ident == "fixed_hash" =>
+            {
+                let trimmed = s.trim_start_matches("0x");
+                let value = u64::from_str_radix(trimmed, 16)
+                    .unwrap_or_else(|_| panic!("invalid fixed_hash literal: {:?}", s));
+                FieldArgs::FixedHash(value)
+            }
+            syn::NestedMetaItem::MetaItem(syn::MetaItem::NameValue(
+                ref ident,
+                syn::Lit::Str(ref s, _),
+            )) if ident == "project" =>
+            {
+                FieldArgs::Project(s.clone())
+            }
+            _ => panic!("unknown #[cross_check_hash] field attribute: {:?}", meta),
+        };
+        // Each of these is mutually exclusive: e.g. `ignore` + `fixed_hash`
+        // would otherwise silently collapse to whichever came last, exactly
+        // the kind of typo'd-attribute-looks-valid mistake this derive
+        // refuses to let through for unknown keys.
+        match args {
+            FieldArgs::Normal => args = parsed,
+            _ => panic!(
+                "conflicting #[cross_check_hash] field attributes: `{}` and `{}` cannot both apply to the same field",
+                args.key(),
+                parsed.key()
+            ),
+        }
+    }
+    args
+}
+
+/// Which `cross_check_runtime::hash::stable` writer a field's type maps to,
+/// if it's one of the primitive integers that mode knows how to canonicalize.
+/// Composite fields fall back to the ordinary recursive `CrossCheckHash`
+/// call even under `#[cross_check_hash(stable)]`.
+///
+/// `usize`/`isize` are the only types with cross-platform *width* ambiguity
+/// (32 bits on some targets, 64 on others), so they alone get widened to a
+/// canonical 64 bits. Fixed-size integers (`u8`/`u16`/.../`i64`) only have
+/// an *endianness* problem, not a width one, so each keeps its own width --
+/// padding a `u8` out to 8 bytes would just be wrong.
+enum StableWriter {
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+    Isize,
+}
+
+fn stable_writer_for(ty: &syn::Ty) -> Option<StableWriter> {
+    let path = match *ty {
+        syn::Ty::Path(None, ref path) => path,
+        _ => return None,
+    };
+    let ident = path.segments.last()?.ident.as_ref();
+    match ident {
+        "u8" | "i8" => Some(StableWriter::U8),
+        "u16" | "i16" => Some(StableWriter::U16),
+        "u32" | "i32" => Some(StableWriter::U32),
+        "u64" | "i64" => Some(StableWriter::U64),
+        "usize" => Some(StableWriter::Usize),
+        "isize" => Some(StableWriter::Isize),
+        _ => None,
+    }
+}
+
+/// `self.<field>` (or `self.<index>` for a tuple struct), as a token tree.
+fn field_ref(idx: usize, field: &syn::Field) -> quote::Tokens {
+    match field.ident {
+        Some(ref ident) => quote! { self.#ident },
+        None => {
+            let idx = syn::Ident::new(idx.to_string());
+            quote! { self.#idx }
+        }
+    }
+}
+
+fn cross_check_hash_impl(ast: &syn::DeriveInput) -> quote::Tokens {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+    let struct_args = parse_struct_args(&ast.attrs);
+
+    let body = if let Some(ref custom_fn) = struct_args.custom_hash {
+        let custom_fn = syn::Ident::new(custom_fn.as_str());
+        quote! { #custom_fn::<XCHA, XCHS>(self, depth) }
+    } else {
+        let fields = match ast.body {
+            syn::Body::Struct(ref data) => data.fields(),
+            syn::Body::Enum(_) => {
+                panic!("#[derive(CrossCheckHash)] does not support enums yet")
+            }
+        };
+        let field_hashes: Vec<quote::Tokens> = fields.iter().enumerate().filter_map(|(idx, field)| {
+            let field_ref = field_ref(idx, field);
+            match parse_field_args(&field.attrs) {
+                FieldArgs::Ignore => None,
+                FieldArgs::FixedHash(value) => Some(quote! {
+                    h.write_u64(#value);
+                }),
+                FieldArgs::NoRecurse => Some(quote! {
+                    h.write_u64(::cross_check_runtime::hash::leaf_hash::<_, XCHS>(&#field_ref));
+                }),
+                FieldArgs::Project(ref proj) => {
+                    let proj_tokens: Vec<syn::TokenTree> = syn::parse_token_trees(proj)
+                        .unwrap_or_else(|e| panic!("invalid project expression {:?}: {}", proj, e));
+                    // Built once: `quote!`'s repetition interpolation consumes
+                    // `proj_tokens` by value, so splicing `#(#proj_tokens)*`
+                    // directly into both branches below would move out of it
+                    // twice.
+                    let projected = quote! { (#field_ref.#(#proj_tokens)*) };
+                    Some(quote! {
+                        h.write_u64(if depth == 0 {
+                            ::cross_check_runtime::hash::leaf_hash::<_, XCHS>(&#projected)
+                        } else {
+                            ::cross_check_runtime::hash::CrossCheckHash::cross_check_hash_depth::<XCHA, XCHS>(&#projected, depth - 1)
+                        });
+                    })
+                }
+                FieldArgs::Normal => {
+                    let stable_writer = if struct_args.stable {
+                        stable_writer_for(&field.ty)
+                    } else {
+                        None
+                    };
+                    Some(match stable_writer {
+                        Some(StableWriter::U8) => quote! {
+                            ::cross_check_runtime::hash::stable::write_u8(&mut h, #field_ref as u8);
+                        },
+                        Some(StableWriter::U16) => quote! {
+                            ::cross_check_runtime::hash::stable::write_u16(&mut h, #field_ref as u16);
+                        },
+                        Some(StableWriter::U32) => quote! {
+                            ::cross_check_runtime::hash::stable::write_u32(&mut h, #field_ref as u32);
+                        },
+                        Some(StableWriter::U64) => quote! {
+                            ::cross_check_runtime::hash::stable::write_u64(&mut h, #field_ref as u64);
+                        },
+                        Some(StableWriter::Usize) => quote! {
+                            ::cross_check_runtime::hash::stable::write_usize(&mut h, #field_ref as usize);
+                        },
+                        Some(StableWriter::Isize) => quote! {
+                            ::cross_check_runtime::hash::stable::write_isize(&mut h, #field_ref as isize);
+                        },
+                        None => quote! {
+                            h.write_u64(if depth == 0 {
+                                ::cross_check_runtime::hash::leaf_hash::<_, XCHS>(&#field_ref)
+                            } else {
+                                ::cross_check_runtime::hash::CrossCheckHash::cross_check_hash_depth::<XCHA, XCHS>(&#field_ref, depth - 1)
+                            });
+                        },
+                    })
+                }
+            }
+        }).collect();
+        // A separator goes *between* fields, not before the first one, so a
+        // single-field struct's hash is unaffected by this delimiting (and
+        // an empty struct still hashes to the hasher's bare seed).
+        let field_hashes = field_hashes.iter().enumerate().map(|(i, field_hash)| {
+            if i == 0 {
+                quote! { #field_hash }
+            } else {
+                quote! {
+                    ::cross_check_runtime::hash::CrossCheckHasher::write_separator(&mut h);
+                    #field_hash
+                }
+            }
+        });
+        quote! {
+            {
+                let mut h = <XCHA as ::cross_check_runtime::hash::CrossCheckHasher>::new();
+                #(#field_hashes)*
+                ::std::hash::Hasher::finish(&h)
+            }
+        }
+    };
+
+    let cross_check_hash_override = struct_args.depth.map(|depth| {
+        let depth = depth as usize;
+        quote! {
+            fn cross_check_hash<XCHA, XCHS>(&self) -> u64
+                where XCHA: ::cross_check_runtime::hash::CrossCheckHasher,
+                      XCHS: ::cross_check_runtime::hash::CrossCheckHasher
+            {
+                self.cross_check_hash_depth::<XCHA, XCHS>(#depth)
+            }
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::cross_check_runtime::hash::CrossCheckHash for #name #ty_generics #where_clause {
+            #cross_check_hash_override
+
+            fn cross_check_hash_depth<XCHA, XCHS>(&self, depth: usize) -> u64
+                where XCHA: ::cross_check_runtime::hash::CrossCheckHasher,
+                      XCHS: ::cross_check_runtime::hash::CrossCheckHasher
+            {
+                #body
+            }
+        }
+    }
+}
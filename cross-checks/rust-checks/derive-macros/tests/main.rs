@@ -7,8 +7,12 @@ extern crate cross_check_derive;
 extern crate cross_check_runtime;
 
 use cross_check_runtime::hash::CrossCheckHash as XCH;
+use cross_check_runtime::hash::CrossCheckHasher;
 use cross_check_runtime::hash::simple::SimpleHasher;
 use cross_check_runtime::hash::djb2::Djb2Hasher;
+use cross_check_runtime::hash::aes::AesHasher;
+use cross_check_runtime::hash::crc32::Crc32Hasher;
+use std::hash::Hasher;
 
 macro_rules! test_struct {
     ([$($attrs:meta),*]
@@ -60,3 +64,237 @@ fn test_simple_one_field() {
             0x12345678_u64);
     });
 }
+
+#[test]
+fn test_field_ignore_and_fixed_hash() {
+    #[derive(CrossCheckHash)]
+    struct Padded {
+        #[cross_check_hash(ignore)]
+        timestamp: u64,
+        #[cross_check_hash(fixed_hash = "0x42")]
+        handle: u64,
+        value: u64,
+    }
+
+    let a = Padded { timestamp: 111, handle: 999, value: 7 };
+    let b = Padded { timestamp: 222, handle: 888, value: 7 };
+    // `timestamp` is ignored and `handle` is pinned to a constant, so two
+    // structs differing only in those fields must cross-check equal.
+    assert_eq!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&a),
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&b));
+}
+
+#[test]
+fn test_field_project() {
+    struct Wrapper {
+        inner: u64,
+    }
+
+    #[derive(CrossCheckHash)]
+    struct Projecting {
+        #[cross_check_hash(project = "inner")]
+        w: Wrapper,
+    }
+
+    let ts = Projecting { w: Wrapper { inner: 0x99 } };
+    // `project` hashes `self.w.inner`, not `self.w` itself (which has no
+    // `CrossCheckHash` impl of its own).
+    assert_eq!(
+        XCH::cross_check_hash::<SimpleHasher, SimpleHasher>(&ts),
+        0x99_u64);
+}
+
+#[test]
+fn test_leaf_hash_is_value_not_address() {
+    let a: u64 = 0xABCD;
+    let b: u64 = 0xABCD;
+    // `a` and `b` are distinct stack slots holding the same value; a leaf
+    // hash of an address would tell them apart, a leaf hash of the value
+    // (what we actually want) must not.
+    assert_eq!(
+        cross_check_runtime::hash::leaf_hash::<u64, Djb2Hasher>(&a),
+        cross_check_runtime::hash::leaf_hash::<u64, Djb2Hasher>(&b));
+}
+
+#[test]
+fn test_no_recurse_field() {
+    #[derive(CrossCheckHash)]
+    struct Handle {
+        #[cross_check_hash(no_recurse)]
+        raw: usize,
+        value: u64,
+    }
+
+    let a = Handle { raw: 0x1000, value: 9 };
+    let b = Handle { raw: 0x2000, value: 9 };
+    // `no_recurse` still hashes the field's own value, so two different
+    // handles produce two different cross-checks...
+    assert!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&a) !=
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&b));
+    // ...but hashing the same struct twice stays deterministic.
+    assert_eq!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&a),
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&a));
+}
+
+#[test]
+fn test_depth_terminates_self_referential_struct() {
+    #[derive(CrossCheckHash)]
+    #[cross_check_hash(depth = 2)]
+    struct Node {
+        value: u64,
+        next: Option<Box<Node>>,
+    }
+
+    fn chain(len: u64) -> Node {
+        let mut node = Node { value: len, next: None };
+        let mut remaining = len;
+        while remaining > 0 {
+            remaining -= 1;
+            node = Node { value: remaining, next: Some(Box::new(node)) };
+        }
+        node
+    }
+
+    // Much deeper than the `depth = 2` budget: hashing it must not hang or
+    // walk the whole chain, and must be deterministic across repeated calls
+    // on the very same (not just an equal) structure.
+    let n = chain(20);
+    assert_eq!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&n),
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&n));
+}
+
+#[test]
+fn test_stable_write_u8_is_one_byte() {
+    struct ByteCountingHasher(usize);
+    impl ::std::hash::Hasher for ByteCountingHasher {
+        fn finish(&self) -> u64 {
+            self.0 as u64
+        }
+        fn write(&mut self, bytes: &[u8]) {
+            self.0 += bytes.len();
+        }
+    }
+
+    let mut h = ByteCountingHasher(0);
+    cross_check_runtime::hash::stable::write_u8(&mut h, 0xAB);
+    // Must write exactly 1 byte, not be padded out to `write_u64`'s 8.
+    assert_eq!(::std::hash::Hasher::finish(&h), 1);
+}
+
+#[test]
+fn test_separator_prevents_field_concatenation_collision() {
+    // The derive writes `write_separator()` between (not before) aggregated
+    // field hashes; without it, two fields' hashes could concatenate into
+    // the same bytes as a differently-shaped aggregate (`{a:1,b:2}` vs
+    // `{a:12}`). Compare a delimited aggregation of the same shape the
+    // derive produces against the naive, unseparated concatenation.
+    let mut separated = Djb2Hasher::new();
+    separated.write_u64(1);
+    separated.write_separator();
+    separated.write_u64(2);
+
+    let mut concatenated = Djb2Hasher::new();
+    concatenated.write_u64(1);
+    concatenated.write_u64(2);
+
+    assert!(Hasher::finish(&separated) != Hasher::finish(&concatenated));
+}
+
+#[test]
+fn test_two_field_struct_uses_separator() {
+    #[derive(CrossCheckHash)]
+    struct Pair {
+        a: u64,
+        b: u64,
+    }
+
+    // The derive's own aggregation for a real two-field struct must match
+    // a hand-rolled computation of each field's own cross-check hash with
+    // a separator between them, not the naive concatenation.
+    let ts = Pair { a: 1, b: 2 };
+
+    let mut expected = Djb2Hasher::new();
+    expected.write_u64(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&1_u64));
+    expected.write_separator();
+    expected.write_u64(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&2_u64));
+
+    assert_eq!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&ts),
+        ::std::hash::Hasher::finish(&expected));
+}
+
+#[test]
+fn test_aes_hasher_sanity() {
+    #[derive(CrossCheckHash)]
+    struct Blob {
+        a: u64,
+        b: u64,
+    }
+
+    let x = Blob { a: 1, b: 2 };
+    let y = Blob { a: 1, b: 3 };
+
+    // Keyed from a fixed compile-time constant, so the same struct must
+    // hash identically every call...
+    assert_eq!(
+        XCH::cross_check_hash::<AesHasher, AesHasher>(&x),
+        XCH::cross_check_hash::<AesHasher, AesHasher>(&x));
+    // ...while two different structs must not collide.
+    assert!(
+        XCH::cross_check_hash::<AesHasher, AesHasher>(&x) !=
+        XCH::cross_check_hash::<AesHasher, AesHasher>(&y));
+}
+
+#[test]
+fn test_aes_hasher_reproducible() {
+    // `AesHasher` is keyed from a fixed constant rather than built to match
+    // a published AES test vector, so this checks reproducibility only:
+    // two fresh hashers fed the same fixed byte sequence must agree. It
+    // compares no stored value, so it does not detect a change to the key
+    // or the mixing step.
+    let mut h1 = AesHasher::new();
+    Hasher::write(&mut h1, b"cross_check");
+    let mut h2 = AesHasher::new();
+    Hasher::write(&mut h2, b"cross_check");
+    assert_eq!(Hasher::finish(&h1), Hasher::finish(&h2));
+}
+
+#[test]
+fn test_crc32_known_vector() {
+    // The standard CRC-32/IEEE check value for the ASCII string
+    // "123456789", reproducible by any conforming implementation
+    // (`zlib`'s `crc32`, Python's `binascii.crc32`, ...).
+    let mut h = Crc32Hasher::new();
+    Hasher::write(&mut h, b"123456789");
+    assert_eq!(Hasher::finish(&h), 0xCBF4_3926_u64);
+}
+
+#[test]
+fn test_stable_preserves_integer_width() {
+    #[derive(CrossCheckHash)]
+    #[cross_check_hash(stable)]
+    struct NarrowField {
+        x: u8,
+    }
+
+    #[derive(CrossCheckHash)]
+    #[cross_check_hash(stable)]
+    struct WideField {
+        x: u64,
+    }
+
+    let narrow = NarrowField { x: 7 };
+    let wide = WideField { x: 7 };
+    // A `u8` field has no cross-platform width ambiguity to canonicalize,
+    // so stable mode must not pad it out to 8 bytes like a `u64` -- or
+    // these two structurally different structs would cross-check equal.
+    assert!(
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&narrow) !=
+        XCH::cross_check_hash::<Djb2Hasher, Djb2Hasher>(&wide));
+}
@@ -0,0 +1,148 @@
+//! An AES-accelerated hasher, for cross-checking large buffers cheaply.
+//!
+//! [`super::djb2::Djb2Hasher`] costs one multiply per byte, which adds up
+//! fast once every cross-check point in translated, buffer-heavy C code is
+//! instrumented. `AesHasher` absorbs a 16-byte block per round using the
+//! hardware AES-NI instruction set when it's available
+//! (`target_feature = "aes"`), and falls back to a cheap multiply-rotate mix
+//! otherwise. The two paths are keyed from the same fixed compile-time
+//! constant, but are *not* bit-compatible with each other -- one runs a real
+//! AES round, the other an unrelated multiply-rotate mix. Cross-checking
+//! against a value computed by the other path requires both sides to be
+//! built with matching `target-feature=+aes` availability; mixing them
+//! produces a false mismatch on identical data.
+
+use std::hash::Hasher;
+use hash::CrossCheckHasher;
+
+/// Fixed across every build of this crate, so the C build and the Rust
+/// translation being cross-checked against it always derive the same key
+/// regardless of which one has AES-NI available.
+const AES_KEY: [u8; 16] = *b"Xch3ckAesKey1234";
+
+/// Streaming AES-accelerated hasher.
+///
+/// Bytes are buffered until a full 16-byte block accumulates, then absorbed
+/// into the running state via one AES round (or its software fallback);
+/// any trailing partial block is zero-padded and absorbed on `finish()`.
+pub struct AesHasher {
+    state: [u8; 16],
+    buf: [u8; 16],
+    buf_len: usize,
+}
+
+impl AesHasher {
+    fn push(&mut self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            let take = (16 - self.buf_len).min(bytes.len());
+            self.buf[self.buf_len..self.buf_len + take].copy_from_slice(&bytes[..take]);
+            self.buf_len += take;
+            bytes = &bytes[take..];
+            if self.buf_len == 16 {
+                let block = self.buf;
+                self.state = aes_round(&self.state, &block);
+                self.buf_len = 0;
+            }
+        }
+    }
+}
+
+impl Default for AesHasher {
+    fn default() -> Self {
+        AesHasher {
+            state: AES_KEY,
+            buf: [0; 16],
+            buf_len: 0,
+        }
+    }
+}
+
+impl Hasher for AesHasher {
+    fn finish(&self) -> u64 {
+        let mut state = self.state;
+        if self.buf_len > 0 {
+            let mut last_block = [0u8; 16];
+            last_block[..self.buf_len].copy_from_slice(&self.buf[..self.buf_len]);
+            state = aes_round(&state, &last_block);
+        }
+        let mut result: u64 = 0;
+        for (i, &byte) in state[..8].iter().enumerate() {
+            result |= (byte as u64) << (8 * i);
+        }
+        result
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.push(bytes);
+    }
+}
+
+impl CrossCheckHasher for AesHasher {
+    fn new() -> Self {
+        AesHasher::default()
+    }
+}
+
+#[cfg(target_feature = "aes")]
+fn aes_round(state: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::{
+        __m128i, _mm_aesenc_si128, _mm_loadu_si128, _mm_storeu_si128, _mm_xor_si128,
+    };
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::{
+        __m128i, _mm_aesenc_si128, _mm_loadu_si128, _mm_storeu_si128, _mm_xor_si128,
+    };
+
+    unsafe {
+        let s = _mm_loadu_si128(state.as_ptr() as *const __m128i);
+        let b = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+        // Mix the incoming block in before the AES round, so repeating
+        // blocks don't just replay the same round key transform.
+        let mixed = _mm_xor_si128(s, b);
+        let out = _mm_aesenc_si128(mixed, b);
+        let mut result = [0u8; 16];
+        _mm_storeu_si128(result.as_mut_ptr() as *mut __m128i, out);
+        result
+    }
+}
+
+/// Software fallback for targets without AES-NI: a golden-ratio
+/// multiply-rotate mix over the state's two 64-bit lanes, keyed the same
+/// way the hardware path is keyed. Weaker than real AES, and *not* the same
+/// function as [`aes_round`] above -- it only has to agree with *itself*
+/// across the two binaries being compared, which requires both of them to
+/// take this same fallback path (see the module-level doc comment).
+#[cfg(not(target_feature = "aes"))]
+fn aes_round(state: &[u8; 16], block: &[u8; 16]) -> [u8; 16] {
+    const MUL1: u64 = 0x9E3779B97F4A7C15;
+    const MUL2: u64 = 0xC2B2AE3D27D4EB4F;
+
+    let mut out = [0u8; 16];
+    for lane in 0..2 {
+        let off = lane * 8;
+        let mut s = read_u64_le(&state[off..off + 8]);
+        let b = read_u64_le(&block[off..off + 8]);
+        s ^= b;
+        s = s.wrapping_mul(MUL1).rotate_left(31);
+        s = s.wrapping_mul(MUL2);
+        write_u64_le(&mut out[off..off + 8], s);
+    }
+    out
+}
+
+#[cfg(not(target_feature = "aes"))]
+fn read_u64_le(bytes: &[u8]) -> u64 {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate() {
+        value |= (byte as u64) << (8 * i);
+    }
+    value
+}
+
+#[cfg(not(target_feature = "aes"))]
+fn write_u64_le(bytes: &mut [u8], value: u64) {
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (value >> (8 * i)) as u8;
+    }
+}
@@ -0,0 +1,72 @@
+//! A standard CRC-32 (IEEE 802.3 polynomial) hasher.
+//!
+//! `SimpleHasher` and `Djb2Hasher` produce values that are only meaningful
+//! to this crate; `AesHasher` is fast but still an opaque 64-bit blob.
+//! `Crc32Hasher` trades some of that for a 32-bit checksum computed with the
+//! same well-known polynomial every `zlib`/`crc32` implementation uses, so a
+//! cross-check log can be eyeballed or reproduced by an external tool
+//! without having to speak this crate's hashing scheme.
+
+use std::hash::Hasher;
+use hash::CrossCheckHasher;
+
+const CRC32_POLY: u32 = 0xEDB8_8320;
+
+const fn build_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ CRC32_POLY
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Computed once at compile time, not per `Crc32Hasher::new()` call: this
+/// table is shared by every cross-check point, rather than each of them
+/// redoing the 256 × 8-iteration setup on the hot path the whole reason
+/// this hasher exists is to stay cheap on.
+static CRC32_TABLE: [u32; 256] = build_table();
+
+/// Standard CRC-32/IEEE hasher: table-driven, byte at a time.
+///
+/// `Hasher::finish` returns the 32-bit checksum zero-extended to `u64`, to
+/// match the rest of this module's `-> u64` hashers.
+pub struct Crc32Hasher {
+    crc: u32,
+}
+
+impl Default for Crc32Hasher {
+    fn default() -> Self {
+        Crc32Hasher { crc: 0xFFFF_FFFF }
+    }
+}
+
+impl Hasher for Crc32Hasher {
+    fn finish(&self) -> u64 {
+        u64::from(self.crc ^ 0xFFFF_FFFF)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            let idx = ((self.crc ^ byte as u32) & 0xFF) as usize;
+            self.crc = (self.crc >> 8) ^ CRC32_TABLE[idx];
+        }
+    }
+}
+
+impl CrossCheckHasher for Crc32Hasher {
+    fn new() -> Self {
+        Crc32Hasher::default()
+    }
+}
@@ -0,0 +1,35 @@
+//! The classic djb2 string hash (`hash = hash * 33 + byte`, seed `5381`).
+//! An aggregator for derived `CrossCheckHash` impls (passed explicitly as
+//! `XCHA`/`XCHS`, like every hasher here): cheap, dependency-free, and good
+//! enough to catch accidental mismatches during development.
+
+use std::hash::Hasher;
+use hash::CrossCheckHasher;
+
+const DJB2_SEED: u64 = 5381;
+
+pub struct Djb2Hasher(u64);
+
+impl Default for Djb2Hasher {
+    fn default() -> Self {
+        Djb2Hasher(DJB2_SEED)
+    }
+}
+
+impl Hasher for Djb2Hasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = self.0.wrapping_mul(33).wrapping_add(byte as u64);
+        }
+    }
+}
+
+impl CrossCheckHasher for Djb2Hasher {
+    fn new() -> Self {
+        Djb2Hasher(DJB2_SEED)
+    }
+}
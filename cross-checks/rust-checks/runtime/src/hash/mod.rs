@@ -0,0 +1,250 @@
+//! The `CrossCheckHash` trait and the hasher backends it can be instantiated
+//! with.
+//!
+//! `#[derive(CrossCheckHash)]` (in the `cross_check_derive` crate) generates
+//! impls of the trait defined here; this module just needs to provide the
+//! trait itself, impls for the primitive types the derive bottoms out on,
+//! and the hashers that plug into the `XCHA`/`XCHS` type parameters.
+
+pub mod simple;
+pub mod djb2;
+pub mod aes;
+pub mod crc32;
+
+use std::hash::Hasher;
+
+/// A `Hasher` that the derived code can build from scratch.
+///
+/// `std::hash::Hasher` has no such constructor (callers are expected to get
+/// one from a `BuildHasher`), but the generated `CrossCheckHash` impls only
+/// ever see the hasher as a type parameter, so they need a way to summon an
+/// instance of it directly.
+pub trait CrossCheckHasher: Hasher {
+    fn new() -> Self;
+
+    /// Write a delimiter between aggregated sub-hashes, so concatenating
+    /// them can't silently merge two different shapes into the same value
+    /// (`{a:1,b:2}` vs `{a:12}`, or `["a","b"]` vs `["ab"]`).
+    ///
+    /// Defaults to writing a single `0xff` byte, mirroring the delimiter
+    /// `core`'s `Hasher::write_str` appends after a string's bytes.
+    /// [`simple::SimpleHasher`] overrides this to a no-op: it's deliberately
+    /// the trivial, collision-prone hasher tests use to assert an exact
+    /// value, and a delimiter would break that.
+    fn write_separator(&mut self) {
+        self.write(&[0xff]);
+    }
+}
+
+/// The recursion budget a top-level `cross_check_hash()` call starts with,
+/// absent an overriding `#[cross_check_hash(depth = N)]` on the type.
+///
+/// Every level of field/pointee recursion consumes one unit of this budget;
+/// once it reaches zero, `CrossCheckHash` impls must stop recursing and
+/// fall back to [`leaf_hash`] instead of walking further into the
+/// structure. This is what lets a cyclic, C-translated data structure
+/// (e.g. a doubly-linked list) be cross-checked without looping forever.
+pub const DEFAULT_XCHECK_DEPTH: usize = 8;
+
+/// Implemented by every type that can produce a cross-check value to
+/// compare against the same value computed on the C side.
+///
+/// The two type parameters let callers separate the hasher used to
+/// aggregate a composite value's fields (`XCHA`) from the one used to hash
+/// individual leaf/scalar values (`XCHS`); most callers just use the same
+/// hasher for both.
+pub trait CrossCheckHash {
+    /// Cross-check `self`, starting from the default recursion budget.
+    fn cross_check_hash<XCHA, XCHS>(&self) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher
+    {
+        self.cross_check_hash_depth::<XCHA, XCHS>(DEFAULT_XCHECK_DEPTH)
+    }
+
+    /// Cross-check `self` with an explicit recursion budget. Implementors
+    /// that recurse into nested `CrossCheckHash` values as a structural hop
+    /// (derived struct impls, slices, ...) must pass `depth - 1` down and
+    /// must check for `depth == 0` themselves before recursing any further,
+    /// falling back to [`leaf_hash`] instead. Pure wrappers (`Box`,
+    /// `Option`) pass `depth` through unchanged.
+    fn cross_check_hash_depth<XCHA, XCHS>(&self, depth: usize) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher;
+}
+
+/// The value substituted for a field once the recursion budget has run out,
+/// or for a field explicitly marked `#[cross_check_hash(no_recurse)]`.
+///
+/// This hashes `value`'s own bytes (e.g. a pointer field's bit pattern)
+/// rather than walking into a (possibly cyclic) pointee: cheap, and two
+/// structurally different handles/pointers still produce different values.
+/// Crucially, it must *not* hash `value`'s address -- two fields holding
+/// the identical value but living at different addresses (or the same
+/// field hashed twice in a row) have to produce the same leaf hash, or this
+/// stops being a value cross-check at all.
+pub fn leaf_hash<T, XCHS: CrossCheckHasher>(value: &T) -> u64 {
+    let mut h = XCHS::new();
+    let bytes = unsafe {
+        ::std::slice::from_raw_parts(value as *const T as *const u8, ::std::mem::size_of::<T>())
+    };
+    h.write(bytes);
+    h.finish()
+}
+
+/// Canonical, endian- and pointer-width-independent byte writers.
+///
+/// `Hasher::write_u64`'s default implementation (and any hand-rolled
+/// `Hasher` that mirrors it, e.g. [`djb2::Djb2Hasher`]) serialize through
+/// `to_ne_bytes`, so the same logical integer hashes differently depending
+/// on the host's endianness; `usize`/`isize` additionally vary in *width*
+/// between 32- and 64-bit targets. The helpers here normalize both away, so
+/// a 32-bit C binary's cross-check values agree with a 64-bit Rust
+/// translation of the same program. Selected per-struct via
+/// `#[cross_check_hash(stable)]`.
+///
+/// Only covers primitive integer fields: `#[cross_check_hash(stable)]`
+/// doesn't change how composite fields hash, so a `str`/`[T]` field still
+/// length-prefixes and writes through the hasher's native (non-canonical)
+/// `write_u64`/`write` regardless of this attribute.
+pub mod stable {
+    use std::hash::Hasher;
+
+    /// Write `value` as 1 byte. Exists so every integer width can go
+    /// through a `stable::write_*` call uniformly; an 8-bit value has no
+    /// endianness of its own to canonicalize.
+    pub fn write_u8<H: Hasher>(h: &mut H, value: u8) {
+        h.write(&[value]);
+    }
+
+    /// Write `value` as 2 fixed-width little-endian bytes.
+    pub fn write_u16<H: Hasher>(h: &mut H, value: u16) {
+        h.write(&[value as u8, (value >> 8) as u8]);
+    }
+
+    /// Write `value` as 4 fixed-width little-endian bytes.
+    pub fn write_u32<H: Hasher>(h: &mut H, value: u32) {
+        h.write(&[
+            value as u8,
+            (value >> 8) as u8,
+            (value >> 16) as u8,
+            (value >> 24) as u8,
+        ]);
+    }
+
+    /// Write `value` as 8 fixed-width little-endian bytes.
+    pub fn write_u64<H: Hasher>(h: &mut H, value: u64) {
+        let bytes = [
+            value as u8,
+            (value >> 8) as u8,
+            (value >> 16) as u8,
+            (value >> 24) as u8,
+            (value >> 32) as u8,
+            (value >> 40) as u8,
+            (value >> 48) as u8,
+            (value >> 56) as u8,
+        ];
+        h.write(&bytes);
+    }
+
+    /// Write `value` as a stable 64-bit quantity, regardless of the host's
+    /// native `usize` width.
+    pub fn write_usize<H: Hasher>(h: &mut H, value: usize) {
+        write_u64(h, value as u64);
+    }
+
+    /// Write `value` as a stable, sign-extended 64-bit quantity, regardless
+    /// of the host's native `isize` width.
+    pub fn write_isize<H: Hasher>(h: &mut H, value: isize) {
+        write_u64(h, (value as i64) as u64);
+    }
+}
+
+macro_rules! impl_cross_check_hash_int {
+    ($ty:ty) => {
+        impl CrossCheckHash for $ty {
+            fn cross_check_hash_depth<XCHA, XCHS>(&self, _depth: usize) -> u64
+                where XCHA: CrossCheckHasher,
+                      XCHS: CrossCheckHasher
+            {
+                let mut h = XCHS::new();
+                h.write_u64(*self as u64);
+                h.finish()
+            }
+        }
+    }
+}
+
+impl_cross_check_hash_int!(u8);
+impl_cross_check_hash_int!(u16);
+impl_cross_check_hash_int!(u32);
+impl_cross_check_hash_int!(u64);
+impl_cross_check_hash_int!(usize);
+impl_cross_check_hash_int!(i8);
+impl_cross_check_hash_int!(i16);
+impl_cross_check_hash_int!(i32);
+impl_cross_check_hash_int!(i64);
+impl_cross_check_hash_int!(isize);
+
+impl<T: CrossCheckHash> CrossCheckHash for [T] {
+    fn cross_check_hash_depth<XCHA, XCHS>(&self, depth: usize) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher
+    {
+        let mut h = XCHA::new();
+        h.write_u64(self.len() as u64);
+        for (i, item) in self.iter().enumerate() {
+            if i > 0 {
+                h.write_separator();
+            }
+            h.write_u64(if depth == 0 {
+                leaf_hash::<T, XCHS>(item)
+            } else {
+                item.cross_check_hash_depth::<XCHA, XCHS>(depth - 1)
+            });
+        }
+        h.finish()
+    }
+}
+
+impl CrossCheckHash for str {
+    fn cross_check_hash_depth<XCHA, XCHS>(&self, _depth: usize) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher
+    {
+        let mut h = XCHA::new();
+        h.write_u64(self.len() as u64);
+        h.write(self.as_bytes());
+        h.finish()
+    }
+}
+
+impl<T: CrossCheckHash> CrossCheckHash for Box<T> {
+    fn cross_check_hash_depth<XCHA, XCHS>(&self, depth: usize) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher
+    {
+        // A `Box` is pure indirection, not a structural hop of its own, so
+        // it passes the budget through unchanged: the cutoff is spent by
+        // the derive's per-field code on the way into this `Box`, not here.
+        (**self).cross_check_hash_depth::<XCHA, XCHS>(depth)
+    }
+}
+
+impl<T: CrossCheckHash> CrossCheckHash for Option<T> {
+    fn cross_check_hash_depth<XCHA, XCHS>(&self, depth: usize) -> u64
+        where XCHA: CrossCheckHasher,
+              XCHS: CrossCheckHasher
+    {
+        let mut h = XCHA::new();
+        match *self {
+            Some(ref value) => {
+                h.write_u64(1);
+                h.write_separator();
+                h.write_u64(value.cross_check_hash_depth::<XCHA, XCHS>(depth));
+            }
+            None => h.write_u64(0),
+        }
+        h.finish()
+    }
+}
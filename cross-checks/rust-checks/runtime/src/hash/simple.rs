@@ -0,0 +1,39 @@
+//! A hasher with no mixing whatsoever: each `write_*` call just replaces the
+//! running state with the new value. It exists purely so tests can assert
+//! an exact cross-check value for a single-field struct without having to
+//! know a real hash function's internals.
+
+use std::hash::Hasher;
+use hash::CrossCheckHasher;
+
+#[derive(Default)]
+pub struct SimpleHasher(u64);
+
+impl Hasher for SimpleHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut value: u64 = 0;
+        for (i, &byte) in bytes.iter().take(8).enumerate() {
+            value |= (byte as u64) << (8 * i);
+        }
+        self.0 = value;
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = i;
+    }
+}
+
+impl CrossCheckHasher for SimpleHasher {
+    fn new() -> Self {
+        SimpleHasher(0)
+    }
+
+    /// A no-op: `SimpleHasher` is intentionally collision-prone (its whole
+    /// point is to let a test assert an exact field value), so it skips the
+    /// delimiter `CrossCheckHasher::write_separator` injects by default.
+    fn write_separator(&mut self) {}
+}
@@ -0,0 +1,7 @@
+//! Runtime support for the code emitted by `#[derive(CrossCheckHash)]`.
+//!
+//! This crate is linked into every cross-checked translation; it has no
+//! dependency on `cross_check_derive` so that plain (non-macro) code can
+//! also produce cross-check values by hand.
+
+pub mod hash;